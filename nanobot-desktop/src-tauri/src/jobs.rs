@@ -0,0 +1,386 @@
+//! Persisted agent jobs. Each invocation of the agent started through
+//! `spawn`/`resume` is modeled as a `JobRecord` checkpointed to disk under
+//! `nanobot_home()/jobs/` (MessagePack via `rmp-serde`, since accumulated
+//! output can get large and pretty JSON would bloat it). This lets a job
+//! survive the desktop app being closed mid-run instead of simply vanishing.
+
+use crate::{base_command, emit_log, nanobot_home, strip_ansi, truncate_line, AgentChunkPayload};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::process::{Child, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::UNIX_EPOCH;
+use tauri::{AppHandle, Emitter};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum JobState {
+    Running,
+    Paused,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct JobRecord {
+    pub(crate) id: String,
+    pub(crate) session_id: String,
+    pub(crate) prompt: String,
+    pub(crate) state: JobState,
+    pub(crate) output: String,
+    pub(crate) created_at: u64,
+    pub(crate) updated_at: u64,
+}
+
+/// A job's live child plus whatever handle is needed to tear down its whole
+/// process tree, not just the immediate process — the same pgid/job-object
+/// machinery `start_process_inner` uses for the agent/gateway processes.
+struct RunningJob {
+    child: Child,
+    #[cfg(unix)]
+    pgid: i32,
+    #[cfg(windows)]
+    job: Option<crate::JobHandle>,
+}
+
+/// Child handles for jobs currently executing in this process, so
+/// `cancel_job` can actually terminate the subprocess tree. Jobs that were
+/// `Running` in a prior process (the app was closed mid-run) have no entry
+/// here even though their record says `Running` until startup re-enqueues
+/// them.
+fn running_children() -> &'static Mutex<HashMap<String, RunningJob>> {
+    static RUNNING: OnceLock<Mutex<HashMap<String, RunningJob>>> = OnceLock::new();
+    RUNNING.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Remove a job from `running_children`, if still present, and terminate its
+/// whole process tree the same way `stop_agent_locked`/`stop_gateway_locked`
+/// do. Returns whether there was actually a live process to terminate.
+fn terminate_running(id: &str) -> bool {
+    let running = running_children().lock().ok().and_then(|mut r| r.remove(id));
+    match running {
+        Some(mut job) => {
+            #[cfg(unix)]
+            crate::terminate_process_group(&mut job.child, job.pgid);
+            #[cfg(windows)]
+            {
+                if let Some(handle) = job.job.as_ref() {
+                    crate::terminate_job(handle, &mut job.child);
+                } else {
+                    let _ = job.child.kill();
+                    let _ = job.child.wait();
+                }
+            }
+            true
+        }
+        None => false,
+    }
+}
+
+fn next_job_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let now = now_secs();
+    let seq = COUNTER.fetch_add(1, Ordering::Relaxed);
+    format!("job-{now}-{seq}")
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn jobs_dir() -> PathBuf {
+    nanobot_home().join("jobs")
+}
+
+fn job_path(id: &str) -> PathBuf {
+    jobs_dir().join(format!("{id}.msgpack"))
+}
+
+fn save_job(record: &JobRecord) -> Result<(), String> {
+    std::fs::create_dir_all(jobs_dir()).map_err(|e| e.to_string())?;
+    let bytes = rmp_serde::to_vec(record).map_err(|e| e.to_string())?;
+    std::fs::write(job_path(&record.id), bytes).map_err(|e| e.to_string())
+}
+
+fn load_job(id: &str) -> Option<JobRecord> {
+    let bytes = std::fs::read(job_path(id)).ok()?;
+    rmp_serde::from_slice(&bytes).ok()
+}
+
+pub(crate) fn get_job(id: &str) -> Option<JobRecord> {
+    load_job(id)
+}
+
+pub(crate) fn list_job_records() -> Vec<JobRecord> {
+    let dir = jobs_dir();
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(e) => e,
+        Err(_) => return Vec::new(),
+    };
+    let mut jobs: Vec<JobRecord> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().and_then(|e| e.to_str()) == Some("msgpack"))
+        .filter_map(|entry| std::fs::read(entry.path()).ok())
+        .filter_map(|bytes| rmp_serde::from_slice::<JobRecord>(&bytes).ok())
+        .collect();
+    jobs.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+    jobs
+}
+
+/// Read a job's subprocess stream line-by-line, strip ANSI per line, and
+/// append it to the shared job record on disk as it arrives (not just at
+/// exit) so a crash mid-run leaves the partial output checkpointed instead
+/// of losing it, and so the chat UI receives each line as an `agent-chunk`
+/// event while `send_agent_message` waits for the job to finish.
+fn spawn_job_reader(
+    app: AppHandle,
+    record: Arc<Mutex<JobRecord>>,
+    stream: String,
+    mut reader: impl BufRead + Send + 'static,
+) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        let mut raw_line = String::new();
+        loop {
+            raw_line.clear();
+            match reader.read_line(&mut raw_line) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    let cleaned = strip_ansi(raw_line.trim_end_matches(['\n', '\r']));
+                    if cleaned.trim().is_empty() {
+                        continue;
+                    }
+                    let snapshot = {
+                        let mut rec = match record.lock() {
+                            Ok(rec) => rec,
+                            Err(_) => return,
+                        };
+                        if !rec.output.is_empty() {
+                            rec.output.push('\n');
+                        }
+                        rec.output.push_str(&cleaned);
+                        rec.updated_at = now_secs();
+                        let _ = save_job(&rec);
+                        rec.clone()
+                    };
+                    emit_log(&app, "agent", cleaned.clone(), &stream);
+                    let _ = app.emit(
+                        "agent-chunk",
+                        AgentChunkPayload {
+                            session_id: snapshot.session_id.clone(),
+                            chunk: cleaned,
+                        },
+                    );
+                    let _ = app.emit("job-updated", snapshot);
+                }
+            }
+        }
+    })
+}
+
+/// Run the agent invocation for a job to completion, reading stdout and
+/// stderr concurrently on separate threads (mirroring `spawn_reader`) so a
+/// subprocess that fills the stderr pipe while we're still draining stdout
+/// can't deadlock us.
+fn execute_job(app: AppHandle, record: JobRecord) {
+    let mut cmd = base_command(&app);
+    cmd.args([
+        "-m",
+        "nanobot",
+        "agent",
+        "--message",
+        &record.prompt,
+        "--session",
+        &record.session_id,
+    ])
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .stdin(Stdio::null());
+    #[cfg(unix)]
+    crate::put_in_new_process_group(&mut cmd);
+    #[cfg(windows)]
+    let job_handle = crate::create_job_object();
+
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            let mut record = record;
+            record.state = JobState::Failed;
+            record.output.push_str(&format!("Failed to start job: {e}"));
+            record.updated_at = now_secs();
+            let _ = save_job(&record);
+            let _ = app.emit("job-updated", record);
+            return;
+        }
+    };
+    #[cfg(windows)]
+    if let Some(job) = job_handle.as_ref() {
+        crate::assign_child_to_job(job, &child);
+    }
+
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let id = record.id.clone();
+    #[cfg(unix)]
+    let pgid = child.id() as i32;
+
+    if let Ok(mut running) = running_children().lock() {
+        running.insert(
+            id.clone(),
+            RunningJob {
+                child,
+                #[cfg(unix)]
+                pgid,
+                #[cfg(windows)]
+                job: job_handle,
+            },
+        );
+    }
+
+    let shared = Arc::new(Mutex::new(record));
+    let mut readers = Vec::new();
+    if let Some(pipe) = stdout {
+        readers.push(spawn_job_reader(
+            app.clone(),
+            shared.clone(),
+            "stdout".to_string(),
+            BufReader::new(pipe),
+        ));
+    }
+    if let Some(pipe) = stderr {
+        readers.push(spawn_job_reader(
+            app.clone(),
+            shared.clone(),
+            "stderr".to_string(),
+            BufReader::new(pipe),
+        ));
+    }
+    for reader in readers {
+        let _ = reader.join();
+    }
+
+    let status = match running_children()
+        .lock()
+        .ok()
+        .and_then(|mut running| running.remove(&id))
+        .map(|mut job| job.child.wait())
+    {
+        Some(status) => status,
+        // The child was already removed from `running_children`, i.e.
+        // `cancel` or `pause_all_running` got to it first; each records the
+        // job's final state itself, so don't overwrite it here.
+        None => return,
+    };
+
+    let mut record = match shared.lock() {
+        Ok(rec) => rec.clone(),
+        Err(_) => return,
+    };
+    record.state = match status {
+        Ok(s) if s.success() => JobState::Completed,
+        _ => JobState::Failed,
+    };
+    record.updated_at = now_secs();
+    let _ = save_job(&record);
+    emit_log(&app, "agent", truncate_line(&record.output, 400), "stdout");
+    let _ = app.emit("job-updated", record);
+}
+
+pub(crate) fn spawn(app: AppHandle, prompt: String, session_id: String) -> JobRecord {
+    let now = now_secs();
+    let record = JobRecord {
+        id: next_job_id(),
+        session_id,
+        prompt,
+        state: JobState::Running,
+        output: String::new(),
+        created_at: now,
+        updated_at: now,
+    };
+    let _ = save_job(&record);
+    let _ = app.emit("job-updated", record.clone());
+    let handle = app.clone();
+    let job = record.clone();
+    std::thread::spawn(move || execute_job(handle, job));
+    record
+}
+
+pub(crate) fn resume(app: AppHandle, id: String) -> Result<JobRecord, String> {
+    let mut record = load_job(&id).ok_or_else(|| format!("unknown job: {id}"))?;
+    if record.state == JobState::Completed {
+        return Ok(record);
+    }
+    record.state = JobState::Running;
+    // The prompt runs again from scratch as a new one-shot invocation, so the
+    // old attempt's output is stale — keeping it would merge two runs into
+    // one transcript as `spawn_job_reader` appends the new lines onto it.
+    record.output.clear();
+    record.updated_at = now_secs();
+    save_job(&record)?;
+    let _ = app.emit("job-updated", record.clone());
+    let handle = app.clone();
+    let job = record.clone();
+    std::thread::spawn(move || execute_job(handle, job));
+    Ok(record)
+}
+
+pub(crate) fn cancel(app: &AppHandle, id: String) -> Result<(), String> {
+    terminate_running(&id);
+    if let Some(mut record) = load_job(&id) {
+        // The job may already have reached a terminal state on its own (it
+        // finished, or failed) between the caller deciding to cancel it and
+        // this call actually running; don't clobber that real outcome with
+        // `Cancelled`.
+        if matches!(
+            record.state,
+            JobState::Completed | JobState::Failed | JobState::Cancelled
+        ) {
+            return Ok(());
+        }
+        record.state = JobState::Cancelled;
+        record.updated_at = now_secs();
+        save_job(&record)?;
+        let _ = app.emit("job-updated", record);
+    }
+    Ok(())
+}
+
+/// Terminate every live job's process tree and flip its still-`Running`
+/// record to `Paused` on disk. Called from the tray "quit" handler so
+/// in-flight work is recorded as interrupted rather than left claiming to be
+/// running forever — and, critically, so the subprocess doesn't outlive the
+/// app as an orphan that `resume_interrupted` would then race on restart.
+pub(crate) fn pause_all_running() {
+    let live_ids: Vec<String> = running_children()
+        .lock()
+        .map(|running| running.keys().cloned().collect())
+        .unwrap_or_default();
+    for id in live_ids {
+        terminate_running(&id);
+    }
+    for mut record in list_job_records() {
+        if record.state == JobState::Running {
+            record.state = JobState::Paused;
+            record.updated_at = now_secs();
+            let _ = save_job(&record);
+        }
+    }
+}
+
+/// Re-enqueue jobs left `Running` or `Paused` from a prior process (the app
+/// was closed, or crashed, mid-run) so long-running work survives restarts
+/// instead of silently vanishing.
+pub(crate) fn resume_interrupted(app: &AppHandle) {
+    for record in list_job_records() {
+        if matches!(record.state, JobState::Running | JobState::Paused) {
+            let _ = resume(app.clone(), record.id);
+        }
+    }
+}
@@ -1,32 +1,111 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod jobs;
+mod search_index;
+mod session_index;
+mod watcher;
+mod workers;
+
 use serde::Serialize;
 use serde_json::{json, Value};
-use std::collections::{HashSet, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::io::{BufReader, Read};
 use std::path::{Component, Path, PathBuf};
 use std::process::{Child, Command, Stdio};
 use std::sync::{Arc, Mutex, OnceLock};
-use std::time::UNIX_EPOCH;
+use std::time::{Duration, Instant, UNIX_EPOCH};
 use tauri::menu::{Menu, MenuItem};
 use tauri::tray::{MouseButton, MouseButtonState, TrayIconEvent};
 use tauri::{AppHandle, Emitter, Manager, State, WindowEvent};
 
+#[cfg(windows)]
+use std::os::windows::io::AsRawHandle;
 #[cfg(windows)]
 use std::os::windows::process::CommandExt;
 
+#[cfg(unix)]
+use std::os::unix::process::CommandExt as _;
+
+#[cfg(windows)]
+use windows_sys::Win32::Foundation::CloseHandle;
+#[cfg(windows)]
+use windows_sys::Win32::System::JobObjects::{
+    AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation,
+    SetInformationJobObject, TerminateJobObject, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+    JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+};
+
 #[cfg(windows)]
 const CREATE_NO_WINDOW: u32 = 0x08000000;
 const MAX_LOG_LINES: usize = 2000;
+// Grace period between SIGTERM/job-close and the hard-kill escalation.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+// Ordered low-to-high so a line's level can be compared against the configured floor.
+const LOG_LEVELS: [&str; 5] = ["DEBUG", "INFO", "WARNING", "ERROR", "CRITICAL"];
 static PRINT_LOGS: OnceLock<bool> = OnceLock::new();
-static SCAN_PROCS: OnceLock<bool> = OnceLock::new();
+static MIN_LOG_LEVEL: Mutex<u8> = Mutex::new(0);
+
+/// A Windows Job Object that owns a spawned child and everything it spawns.
+/// Closing (or explicitly terminating) the handle kills the whole tree.
+#[cfg(windows)]
+pub(crate) struct JobHandle(isize);
+
+#[cfg(windows)]
+unsafe impl Send for JobHandle {}
+
+#[cfg(windows)]
+impl Drop for JobHandle {
+    fn drop(&mut self) {
+        unsafe {
+            CloseHandle(self.0);
+        }
+    }
+}
+
+#[cfg(windows)]
+pub(crate) fn create_job_object() -> Option<JobHandle> {
+    unsafe {
+        let job = CreateJobObjectW(std::ptr::null(), std::ptr::null());
+        if job == 0 {
+            return None;
+        }
+        let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = std::mem::zeroed();
+        info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+        SetInformationJobObject(
+            job,
+            JobObjectExtendedLimitInformation,
+            &info as *const _ as *const _,
+            std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+        );
+        Some(JobHandle(job))
+    }
+}
+
+#[cfg(windows)]
+pub(crate) fn assign_child_to_job(job: &JobHandle, child: &Child) -> bool {
+    unsafe { AssignProcessToJobObject(job.0, child.as_raw_handle() as isize) != 0 }
+}
 
 #[derive(Default)]
 struct ProcState {
     agent: Option<Child>,
     gateway: Option<Child>,
+    #[cfg(unix)]
+    agent_pgid: Option<i32>,
+    #[cfg(unix)]
+    gateway_pgid: Option<i32>,
+    #[cfg(windows)]
+    agent_job: Option<JobHandle>,
+    #[cfg(windows)]
+    gateway_job: Option<JobHandle>,
+    gateway_ready: bool,
     logs: VecDeque<LogPayload>,
     emit_logs: bool,
+    /// Whether the most recent line processed for a given `"{kind}:{stream}"`
+    /// was dropped by `set_log_level` filtering, so a later continuation line
+    /// for that same (now-absent) entry can be dropped too instead of being
+    /// merged onto an unrelated, already-displayed entry.
+    filtered_tail: HashMap<String, bool>,
 }
 
 #[derive(Serialize, Clone)]
@@ -34,6 +113,8 @@ struct LogPayload {
     kind: String,
     line: String,
     stream: String,
+    level: Option<String>,
+    ts: Option<u64>,
 }
 
 #[derive(Serialize, Clone)]
@@ -41,10 +122,26 @@ struct ProcessExitPayload {
     kind: String,
 }
 
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AgentChunkPayload {
+    pub(crate) session_id: String,
+    pub(crate) chunk: String,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct AgentDonePayload {
+    session_id: String,
+    text: String,
+}
+
 #[derive(Serialize)]
 struct StatusPayload {
     agent: bool,
     gateway: bool,
+    #[serde(rename = "gatewayReady")]
+    gateway_ready: bool,
 }
 
 #[derive(Serialize, Clone)]
@@ -107,6 +204,18 @@ struct SessionInfo {
     path: String,
     size: Option<u64>,
     modified: Option<u64>,
+    message_count: usize,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct SessionSearchResultPayload {
+    id: String,
+    session: String,
+    role: String,
+    content: String,
+    created_at: String,
+    line: usize,
 }
 
 fn repo_root() -> PathBuf {
@@ -149,7 +258,7 @@ fn home_dir() -> Option<PathBuf> {
     }
 }
 
-fn nanobot_home() -> PathBuf {
+pub(crate) fn nanobot_home() -> PathBuf {
     if let Some(home) = std::env::var_os("NANOBOT_HOME") {
         let trimmed = home.to_string_lossy().trim().to_string();
         if !trimmed.is_empty() {
@@ -162,7 +271,7 @@ fn nanobot_home() -> PathBuf {
     repo_root().join(".nanobot")
 }
 
-fn config_path() -> PathBuf {
+pub(crate) fn config_path() -> PathBuf {
     nanobot_home().join("config.json")
 }
 
@@ -189,6 +298,183 @@ fn read_config_workspace() -> Option<PathBuf> {
     Some(expand_tilde(workspace))
 }
 
+const DEFAULT_GATEWAY_HOST: &str = "127.0.0.1";
+const DEFAULT_GATEWAY_PORT: u16 = 8765;
+const GATEWAY_READY_TIMEOUT: Duration = Duration::from_secs(20);
+const GATEWAY_READY_POLL_INTERVAL: Duration = Duration::from_millis(250);
+const DEFAULT_STARTUP_MAX_ATTEMPTS: u32 = 3;
+const DEFAULT_STARTUP_BACKOFF_MS: u64 = 1000;
+const STARTUP_BACKOFF_CAP_MS: u64 = 4000;
+
+fn read_gateway_endpoint() -> (String, u16) {
+    let fallback = (DEFAULT_GATEWAY_HOST.to_string(), DEFAULT_GATEWAY_PORT);
+    let contents = match std::fs::read_to_string(config_path()) {
+        Ok(c) => c,
+        Err(_) => return fallback,
+    };
+    let parsed: Value = match serde_json::from_str(&contents) {
+        Ok(v) => v,
+        Err(_) => return fallback,
+    };
+    let gateway = parsed.get("gateway");
+    let host = gateway
+        .and_then(|g| g.get("host"))
+        .and_then(Value::as_str)
+        .unwrap_or(DEFAULT_GATEWAY_HOST)
+        .to_string();
+    let port = gateway
+        .and_then(|g| g.get("port"))
+        .and_then(Value::as_u64)
+        .and_then(|p| u16::try_from(p).ok())
+        .unwrap_or(DEFAULT_GATEWAY_PORT);
+    (host, port)
+}
+
+fn probe_gateway_ready(host: &str, port: u16) -> bool {
+    use std::net::ToSocketAddrs;
+    let addrs = match (host, port).to_socket_addrs() {
+        Ok(a) => a,
+        Err(_) => return false,
+    };
+    addrs
+        .into_iter()
+        .any(|addr| std::net::TcpStream::connect_timeout(&addr, Duration::from_millis(300)).is_ok())
+}
+
+/// Poll the gateway's listening endpoint in the background and emit
+/// `process-ready` once it accepts connections, instead of trusting the
+/// "started" log line emitted the instant the child spawns. If the port
+/// never comes up within `GATEWAY_READY_TIMEOUT`, treat the gateway as
+/// failed and stop it rather than leaving it reported as "running".
+fn spawn_gateway_readiness_probe(app: AppHandle, state: Arc<Mutex<ProcState>>) {
+    std::thread::spawn(move || {
+        let (host, port) = read_gateway_endpoint();
+        let deadline = Instant::now() + GATEWAY_READY_TIMEOUT;
+        loop {
+            if probe_gateway_ready(&host, port) {
+                if let Ok(mut guard) = state.lock() {
+                    guard.gateway_ready = true;
+                }
+                emit_log(&app, "gateway", "Gateway is ready".to_string(), "stdout");
+                let _ = app.emit(
+                    "process-ready",
+                    ProcessExitPayload {
+                        kind: "gateway".to_string(),
+                    },
+                );
+                return;
+            }
+            let still_running = state
+                .lock()
+                .map(|mut g| refresh_child(&mut g.gateway))
+                .unwrap_or(false);
+            if !still_running {
+                return;
+            }
+            if Instant::now() >= deadline {
+                break;
+            }
+            std::thread::sleep(GATEWAY_READY_POLL_INTERVAL);
+        }
+        let failure = format!(
+            "Gateway did not become ready on {host}:{port} within {}s; treating as failed.",
+            GATEWAY_READY_TIMEOUT.as_secs()
+        );
+        emit_log(&app, "gateway", failure.clone(), "stderr");
+        workers::mark_dead(&app, "gateway", "gateway", failure);
+        if let Ok(mut guard) = state.lock() {
+            guard.gateway_ready = false;
+            stop_gateway_locked(&mut guard);
+        }
+        let _ = app.emit(
+            "process-exit",
+            ProcessExitPayload {
+                kind: "gateway".to_string(),
+            },
+        );
+    });
+}
+
+fn read_startup_settings() -> (u32, u64) {
+    let fallback = (DEFAULT_STARTUP_MAX_ATTEMPTS, DEFAULT_STARTUP_BACKOFF_MS);
+    let contents = match std::fs::read_to_string(config_path()) {
+        Ok(c) => c,
+        Err(_) => return fallback,
+    };
+    let parsed: Value = match serde_json::from_str(&contents) {
+        Ok(v) => v,
+        Err(_) => return fallback,
+    };
+    let startup = parsed.get("startup");
+    let max_attempts = startup
+        .and_then(|s| s.get("max_attempts"))
+        .and_then(Value::as_u64)
+        .map(|v| v as u32)
+        .unwrap_or(DEFAULT_STARTUP_MAX_ATTEMPTS)
+        .max(1);
+    let backoff_ms = startup
+        .and_then(|s| s.get("backoff_ms"))
+        .and_then(Value::as_u64)
+        .unwrap_or(DEFAULT_STARTUP_BACKOFF_MS);
+    (max_attempts, backoff_ms)
+}
+
+/// Start `kind`, retrying with exponential backoff (`backoff_ms`, `2x`,
+/// `4x`, ... capped at `STARTUP_BACKOFF_CAP_MS`) up to the configured
+/// attempt count. A process that fails to spawn once is often just
+/// contending with a slow filesystem or a port not yet released from the
+/// previous run, so a single failed attempt shouldn't be fatal.
+fn start_with_backoff(kind: &str, state: &Arc<Mutex<ProcState>>, app: &AppHandle) -> Result<(), String> {
+    let (max_attempts, base_backoff) = read_startup_settings();
+    let mut last_err = String::new();
+    for attempt in 1..=max_attempts {
+        match start_process_inner(kind, state, app) {
+            Ok(()) => return Ok(()),
+            Err(e) => {
+                last_err = e;
+                emit_log(
+                    app,
+                    kind,
+                    format!(
+                        "Start attempt {attempt}/{max_attempts} for {kind} failed: {last_err}"
+                    ),
+                    "stderr",
+                );
+                if attempt == max_attempts {
+                    break;
+                }
+                let delay = base_backoff
+                    .saturating_mul(1u64 << (attempt - 1))
+                    .min(STARTUP_BACKOFF_CAP_MS);
+                std::thread::sleep(Duration::from_millis(delay));
+            }
+        }
+    }
+    Err(last_err)
+}
+
+/// Block until the gateway's readiness probe (spawned by
+/// `start_process_inner`) marks it ready, or until it dies or
+/// `GATEWAY_READY_TIMEOUT` elapses. Used to gate the agent's auto-launch on
+/// the gateway actually being reachable, instead of racing both processes'
+/// startups.
+fn wait_for_gateway_ready(state: &Arc<Mutex<ProcState>>) -> bool {
+    let deadline = Instant::now() + GATEWAY_READY_TIMEOUT;
+    loop {
+        let (ready, running) = state
+            .lock()
+            .map(|g| (g.gateway_ready, g.gateway.is_some()))
+            .unwrap_or((false, false));
+        if ready {
+            return true;
+        }
+        if !running || Instant::now() >= deadline {
+            return false;
+        }
+        std::thread::sleep(GATEWAY_READY_POLL_INTERVAL);
+    }
+}
+
 fn resource_root_candidates(app: &AppHandle) -> Vec<PathBuf> {
     let mut out = Vec::new();
     let mut seen: HashSet<PathBuf> = HashSet::new();
@@ -312,7 +598,7 @@ fn build_pythonpath(app: &AppHandle, use_embedded: bool) -> Option<String> {
     std::env::join_paths(paths).ok().and_then(|p| p.into_string().ok())
 }
 
-fn base_command(app: &AppHandle) -> Command {
+pub(crate) fn base_command(app: &AppHandle) -> Command {
     let embedded_python = embedded_python_exe(app);
     let venv_python = local_venv_python();
     let use_embedded = embedded_python.is_some();
@@ -413,23 +699,23 @@ fn run_onboard_inner(app: &AppHandle) -> Result<(), String> {
     }
 }
 
-fn workspace_root() -> PathBuf {
+pub(crate) fn workspace_root() -> PathBuf {
     read_config_workspace().unwrap_or_else(|| nanobot_home().join("workspace"))
 }
 
-fn workspace_skills_dir() -> PathBuf {
+pub(crate) fn workspace_skills_dir() -> PathBuf {
     workspace_root().join("skills")
 }
 
-fn workspace_memory_dir() -> PathBuf {
+pub(crate) fn workspace_memory_dir() -> PathBuf {
     workspace_root().join("memory")
 }
 
-fn sessions_dir() -> PathBuf {
+pub(crate) fn sessions_dir() -> PathBuf {
     nanobot_home().join("sessions")
 }
 
-fn validate_skill_name(name: &str) -> Result<(), String> {
+pub(crate) fn validate_skill_name(name: &str) -> Result<(), String> {
     let mut comps = Path::new(name).components();
     match (comps.next(), comps.next()) {
         (Some(Component::Normal(_)), None) => Ok(()),
@@ -454,7 +740,7 @@ fn is_date_memory_name(name: &str) -> bool {
         && bytes[12] == b'd'
 }
 
-fn validate_memory_name(name: &str) -> Result<(), String> {
+pub(crate) fn validate_memory_name(name: &str) -> Result<(), String> {
     if name == "MEMORY.md" {
         return Ok(());
     }
@@ -464,17 +750,89 @@ fn validate_memory_name(name: &str) -> Result<(), String> {
     Err("invalid memory name".to_string())
 }
 
+fn log_level_rank(level: &str) -> u8 {
+    LOG_LEVELS
+        .iter()
+        .position(|l| *l == level)
+        .map(|idx| idx as u8)
+        .unwrap_or(1)
+}
+
+/// Detect the loguru level token (`"... | INFO     | ..."`-style sinks pad
+/// the level to 8 columns, so we match on the substring rather than an
+/// exact split) anywhere in the line.
+fn detect_log_level(line: &str) -> Option<String> {
+    LOG_LEVELS
+        .iter()
+        .rev()
+        .find(|level| line.contains(&format!("| {level}")))
+        .map(|level| level.to_string())
+}
+
+/// Parse the leading `YYYY-MM-DD HH:MM:SS` timestamp loguru prefixes every
+/// line with, ignoring sub-second precision and any timezone suffix.
+fn detect_log_timestamp(line: &str) -> Option<u64> {
+    let ts_part = line.split('|').next()?.trim();
+    let (date_part, time_part) = ts_part.split_once(' ')?;
+    let mut date = date_part.splitn(3, '-');
+    let year: i64 = date.next()?.parse().ok()?;
+    let month: i64 = date.next()?.parse().ok()?;
+    let day: i64 = date.next()?.parse().ok()?;
+    let time_main = time_part
+        .split(|c| c == '.' || c == '+')
+        .next()
+        .unwrap_or(time_part);
+    let mut time = time_main.splitn(3, ':');
+    let hour: i64 = time.next()?.parse().ok()?;
+    let minute: i64 = time.next()?.parse().ok()?;
+    let second: i64 = time.next()?.parse().ok()?;
+    if !(1970..=9999).contains(&year) || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    let days = days_since_epoch(year, month, day);
+    u64::try_from(days * 86_400 + hour * 3_600 + minute * 60 + second).ok()
+}
+
+/// Howard Hinnant's `days_from_civil`, giving days since 1970-01-01 for a
+/// Gregorian calendar date without pulling in a datetime crate.
+fn days_since_epoch(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
 fn emit_log(app: &AppHandle, kind: &str, line: String, stream: &str) {
+    let level = detect_log_level(&line);
+    let ts = detect_log_timestamp(&line);
+    let min_rank = *MIN_LOG_LEVEL.lock().unwrap_or_else(|e| e.into_inner());
+    let rank = level.as_deref().map(log_level_rank).unwrap_or(1);
     let payload = LogPayload {
         kind: kind.to_string(),
         line,
         stream: stream.to_string(),
+        level,
+        ts,
     };
     if *PRINT_LOGS.get_or_init(|| std::env::var_os("NANOBOT_TAURI_LOG_STDOUT").is_some()) {
         println!("[{kind}][{stream}] {}", payload.line);
     }
+    if kind == "agent" || kind == "gateway" {
+        workers::touch(app, kind, kind);
+    }
+    let tail_key = format!("{kind}:{stream}");
+    if rank < min_rank {
+        if let Ok(mut guard) = app.state::<Arc<Mutex<ProcState>>>().lock() {
+            guard.filtered_tail.insert(tail_key, true);
+        }
+        return;
+    }
     let mut should_emit = false;
     if let Ok(mut guard) = app.state::<Arc<Mutex<ProcState>>>().lock() {
+        guard.filtered_tail.insert(tail_key, false);
         guard.logs.push_back(payload.clone());
         if guard.logs.len() > MAX_LOG_LINES {
             guard.logs.pop_front();
@@ -486,6 +844,55 @@ fn emit_log(app: &AppHandle, kind: &str, line: String, stream: &str) {
     }
 }
 
+/// Attach a traceback continuation line (one without a loguru level prefix)
+/// to the most recently retained entry for this `kind`/`stream`, instead of
+/// emitting it as its own orphan log line.
+fn append_log_continuation(app: &AppHandle, kind: &str, stream: &str, line: &str) {
+    let tail_key = format!("{kind}:{stream}");
+    let mut updated = None;
+    if let Ok(mut guard) = app.state::<Arc<Mutex<ProcState>>>().lock() {
+        if guard.filtered_tail.get(&tail_key).copied().unwrap_or(false) {
+            return;
+        }
+        let emit_logs = guard.emit_logs;
+        if let Some(last) = guard
+            .logs
+            .iter_mut()
+            .rev()
+            .find(|entry| entry.kind == kind && entry.stream == stream)
+        {
+            last.line.push('\n');
+            last.line.push_str(line);
+            updated = Some((last.clone(), emit_logs));
+        }
+    }
+    if let Some((payload, should_emit)) = updated {
+        if should_emit {
+            let _ = app.emit("process-log", payload);
+        }
+    }
+}
+
+fn route_log_line(app: &AppHandle, kind: &str, stream: &str, line: String) {
+    if detect_log_level(&line).is_some() {
+        emit_log(app, kind, line, stream);
+    } else {
+        append_log_continuation(app, kind, stream, &line);
+    }
+}
+
+#[tauri::command]
+fn set_log_level(min_level: String) -> Result<(), String> {
+    let rank = LOG_LEVELS
+        .iter()
+        .position(|l| l.eq_ignore_ascii_case(&min_level))
+        .ok_or_else(|| format!("unknown log level: {min_level}"))?;
+    if let Ok(mut guard) = MIN_LOG_LEVEL.lock() {
+        *guard = rank as u8;
+    }
+    Ok(())
+}
+
 fn spawn_reader(
     app: AppHandle,
     kind: String,
@@ -514,21 +921,21 @@ fn spawn_reader(
                 let line = pending[..split_at].trim_end().to_string();
                 pending = pending[split_at + 1..].to_string();
                 if !line.trim().is_empty() {
-                    emit_log(&app, &kind, line, &stream);
+                    route_log_line(&app, &kind, &stream, line);
                 }
             }
 
             if pending.len() > 2048 {
                 let line = pending.trim_end().to_string();
                 if !line.trim().is_empty() {
-                    emit_log(&app, &kind, line, &stream);
+                    route_log_line(&app, &kind, &stream, line);
                 }
                 pending.clear();
             }
         }
 
         if !pending.trim().is_empty() {
-            emit_log(&app, &kind, pending.trim_end().to_string(), &stream);
+            route_log_line(&app, &kind, &stream, pending.trim_end().to_string());
         }
         emit_log(
             &app,
@@ -551,96 +958,113 @@ fn refresh_child(child: &mut Option<Child>) -> bool {
     false
 }
 
-fn kill_process_tree(pid: u32) {
-    #[cfg(windows)]
-    {
-        let _ = Command::new("taskkill")
-            .args(["/PID", &pid.to_string(), "/T", "/F"])
-            .status();
+/// Send SIGTERM to the whole process group, wait up to `SHUTDOWN_GRACE` for
+/// the child to exit on its own, then escalate to SIGKILL. `pgid` is
+/// authoritative (set via `pre_exec` at spawn time) so this never has to
+/// guess at ownership via command-line pattern matching.
+#[cfg(unix)]
+pub(crate) fn terminate_process_group(child: &mut Child, pgid: i32) {
+    unsafe {
+        libc::killpg(pgid, libc::SIGTERM);
+    }
+    let deadline = Instant::now() + SHUTDOWN_GRACE;
+    loop {
+        match child.try_wait() {
+            Ok(Some(_)) | Err(_) => return,
+            Ok(None) => {
+                if Instant::now() >= deadline {
+                    break;
+                }
+                std::thread::sleep(Duration::from_millis(100));
+            }
+        }
     }
-    #[cfg(not(windows))]
-    {
-        // Best-effort cross-platform cleanup
-        let _ = Command::new("pkill")
-            .args(["-TERM", "-P", &pid.to_string()])
-            .status();
-        let _ = Command::new("kill")
-            .args(["-TERM", &pid.to_string()])
-            .status();
+    unsafe {
+        libc::killpg(pgid, libc::SIGKILL);
     }
+    let _ = child.wait();
 }
 
-fn kill_matching_processes(kind: &str) {
-    #[cfg(windows)]
-    {
-        let pattern = match kind {
-            "agent" => "nanobot agent",
-            "gateway" => "nanobot gateway",
-            _ => return,
-        };
-        let cmd = format!(
-            r#"Get-CimInstance Win32_Process | Where-Object {{ $_.CommandLine -match '{}' }} | Stop-Process -Id {{$_.ProcessId}} -Force"#,
-            pattern
-        );
-        let mut ps = Command::new("powershell");
-        ps.args(["-NoProfile", "-WindowStyle", "Hidden", "-Command", &cmd]);
-        ps.creation_flags(CREATE_NO_WINDOW);
-        let _ = ps.status();
-    }
-    #[cfg(not(windows))]
-    {
-        let pattern = match kind {
-            "agent" => "nanobot agent",
-            "gateway" => "nanobot gateway",
-            _ => return,
-        };
-        // pkill -f matches full command line; best-effort cleanup.
-        let _ = Command::new("pkill").args(["-f", pattern]).status();
+/// Terminate the job object (and therefore every process it owns) and wait
+/// up to `SHUTDOWN_GRACE` for the handle to report the child as exited.
+#[cfg(windows)]
+pub(crate) fn terminate_job(job: &JobHandle, child: &mut Child) {
+    unsafe {
+        TerminateJobObject(job.0, 1);
+    }
+    let deadline = Instant::now() + SHUTDOWN_GRACE;
+    loop {
+        match child.try_wait() {
+            Ok(Some(_)) | Err(_) => return,
+            Ok(None) => {
+                if Instant::now() >= deadline {
+                    break;
+                }
+                std::thread::sleep(Duration::from_millis(100));
+            }
+        }
     }
+    let _ = child.kill();
+    let _ = child.wait();
 }
 
-fn is_matching_process_running(kind: &str) -> bool {
-    let pattern = match kind {
-        "agent" => "nanobot agent",
-        "gateway" => "nanobot gateway",
-        _ => return false,
-    };
-    #[cfg(windows)]
-    {
-        let cmd = format!(
-            "if (Get-CimInstance Win32_Process | Where-Object {{ $_.CommandLine -match '{}' }} | Select-Object -First 1) {{ exit 0 }} else {{ exit 1 }}",
-            pattern
-        );
-        let mut ps = Command::new("powershell");
-        ps.args(["-NoProfile", "-WindowStyle", "Hidden", "-Command", &cmd]);
-        ps.creation_flags(CREATE_NO_WINDOW);
-        return ps
-            .status()
-            .map(|s| s.success())
-            .unwrap_or(false);
+fn stop_agent_locked(guard: &mut ProcState) {
+    if let Some(mut child) = guard.agent.take() {
+        #[cfg(unix)]
+        {
+            if let Some(pgid) = guard.agent_pgid.take() {
+                terminate_process_group(&mut child, pgid);
+                return;
+            }
+        }
+        #[cfg(windows)]
+        {
+            if let Some(job) = guard.agent_job.take() {
+                terminate_job(&job, &mut child);
+                return;
+            }
+        }
+        let _ = child.kill();
     }
-    #[cfg(not(windows))]
-    {
-        return Command::new("pgrep")
-            .args(["-f", pattern])
-            .status()
-            .map(|s| s.success())
-            .unwrap_or(false);
+}
+
+fn stop_gateway_locked(guard: &mut ProcState) {
+    if let Some(mut child) = guard.gateway.take() {
+        #[cfg(unix)]
+        {
+            if let Some(pgid) = guard.gateway_pgid.take() {
+                terminate_process_group(&mut child, pgid);
+                return;
+            }
+        }
+        #[cfg(windows)]
+        {
+            if let Some(job) = guard.gateway_job.take() {
+                terminate_job(&job, &mut child);
+                return;
+            }
+        }
+        let _ = child.kill();
     }
 }
 
 fn stop_all_processes(state: &Arc<Mutex<ProcState>>) {
     if let Ok(mut guard) = state.lock() {
-        if let Some(mut child) = guard.agent.take() {
-            let pid = child.id();
-            let _ = child.kill();
-            kill_process_tree(pid);
-        }
-        if let Some(mut child) = guard.gateway.take() {
-            let pid = child.id();
-            let _ = child.kill();
-            kill_process_tree(pid);
-        }
+        stop_agent_locked(&mut guard);
+        stop_gateway_locked(&mut guard);
+    }
+}
+
+/// Put the spawned child in its own process group so it (and any Python
+/// children it forks) can be terminated as a unit via `killpg`, instead of
+/// relying on `pkill`/`pgrep` pattern matching against the command line.
+#[cfg(unix)]
+pub(crate) fn put_in_new_process_group(cmd: &mut Command) {
+    unsafe {
+        cmd.pre_exec(|| {
+            libc::setpgid(0, 0);
+            Ok(())
+        });
     }
 }
 
@@ -673,6 +1097,11 @@ fn start_process_inner(
                 .stdout(Stdio::piped())
                 .stderr(Stdio::piped())
                 .stdin(Stdio::piped());
+            #[cfg(unix)]
+            put_in_new_process_group(&mut cmd);
+            #[cfg(windows)]
+            let job = create_job_object();
+
             let mut child = cmd.spawn().map_err(|e| {
                 emit_log(
                     app,
@@ -680,8 +1109,13 @@ fn start_process_inner(
                     format!("Failed to start agent: {e}"),
                     "stderr",
                 );
+                workers::mark_dead(app, "agent", "agent", e.to_string());
                 e.to_string()
             })?;
+            #[cfg(windows)]
+            if let Some(job) = job.as_ref() {
+                assign_child_to_job(job, &child);
+            }
 
             if let Some(stdout) = child.stdout.take() {
                 spawn_reader(
@@ -702,6 +1136,14 @@ fn start_process_inner(
 
             {
                 let mut guard = state.lock().map_err(|_| "state lock".to_string())?;
+                #[cfg(unix)]
+                {
+                    guard.agent_pgid = Some(child.id() as i32);
+                }
+                #[cfg(windows)]
+                {
+                    guard.agent_job = job;
+                }
                 guard.agent = Some(child);
             }
             emit_log(app, "agent", "Agent started".to_string(), "stdout");
@@ -715,6 +1157,11 @@ fn start_process_inner(
             if std::env::var_os("NANOBOT_GATEWAY_VERBOSE").is_some() {
                 cmd.arg("--verbose");
             }
+            #[cfg(unix)]
+            put_in_new_process_group(&mut cmd);
+            #[cfg(windows)]
+            let job = create_job_object();
+
             let mut child = cmd.spawn().map_err(|e| {
                 emit_log(
                     app,
@@ -722,8 +1169,13 @@ fn start_process_inner(
                     format!("Failed to start gateway: {e}"),
                     "stderr",
                 );
+                workers::mark_dead(app, "gateway", "gateway", e.to_string());
                 e.to_string()
             })?;
+            #[cfg(windows)]
+            if let Some(job) = job.as_ref() {
+                assign_child_to_job(job, &child);
+            }
 
             if let Some(stdout) = child.stdout.take() {
                 spawn_reader(
@@ -744,9 +1196,19 @@ fn start_process_inner(
 
             {
                 let mut guard = state.lock().map_err(|_| "state lock".to_string())?;
+                #[cfg(unix)]
+                {
+                    guard.gateway_pgid = Some(child.id() as i32);
+                }
+                #[cfg(windows)]
+                {
+                    guard.gateway_job = job;
+                }
                 guard.gateway = Some(child);
+                guard.gateway_ready = false;
             }
             emit_log(app, "gateway", "Gateway started".to_string(), "stdout");
+            spawn_gateway_readiness_probe(app.clone(), state.clone());
         }
         _ => return Err("unknown process".to_string()),
     }
@@ -756,25 +1218,14 @@ fn start_process_inner(
 #[tauri::command]
 fn get_status(state: State<Arc<Mutex<ProcState>>>) -> StatusPayload {
     let mut guard = state.lock().expect("state");
-    let agent_managed = refresh_child(&mut guard.agent);
-    let gateway_managed = refresh_child(&mut guard.gateway);
-    let scan = *SCAN_PROCS
-        .get_or_init(|| std::env::var_os("NANOBOT_SCAN_PROCS").is_some());
-    let agent = if agent_managed {
-        true
-    } else if scan {
-        is_matching_process_running("agent")
-    } else {
-        false
-    };
-    let gateway = if gateway_managed {
-        true
-    } else if scan {
-        is_matching_process_running("gateway")
-    } else {
-        false
-    };
-    StatusPayload { agent, gateway }
+    let agent = refresh_child(&mut guard.agent);
+    let gateway = refresh_child(&mut guard.gateway);
+    let gateway_ready = gateway && guard.gateway_ready;
+    StatusPayload {
+        agent,
+        gateway,
+        gateway_ready,
+    }
 }
 
 #[tauri::command]
@@ -994,69 +1445,20 @@ fn read_session_file(
     offset: usize,
     query: Option<&str>,
 ) -> Result<Vec<SessionMessagePayload>, String> {
-    let path = sessions_dir().join(name);
-    let data = match std::fs::read_to_string(&path) {
-        Ok(s) => s,
-        Err(_) => return Ok(Vec::new()),
-    };
-    let mut rows: Vec<SessionMessagePayload> = Vec::new();
-    let lower_query = query.map(|q| q.to_lowercase());
-
-    for (idx, line) in data.lines().enumerate() {
-        let trimmed = line.trim();
-        if trimmed.is_empty() {
-            continue;
-        }
-        let val: Value = match serde_json::from_str(trimmed) {
-            Ok(v) => v,
-            Err(_) => continue,
-        };
-        if val.get("_type").is_some() {
-            continue;
-        }
-        let content = val
-            .get("content")
-            .and_then(Value::as_str)
-            .unwrap_or("")
-            .to_string();
-        if content.is_empty() {
-            continue;
-        }
-        if let Some(q) = lower_query.as_ref() {
-            if !content.to_lowercase().contains(q) {
-                continue;
-            }
-        }
-        let role = val
-            .get("role")
-            .and_then(Value::as_str)
-            .unwrap_or("system")
-            .to_string();
-        let created_at = val
-            .get("timestamp")
-            .or_else(|| val.get("created_at"))
-            .or_else(|| val.get("updated_at"))
-            .and_then(Value::as_str)
-            .unwrap_or("unknown")
-            .to_string();
-
-        rows.push(SessionMessagePayload {
-            id: format!("{}-{}", created_at, idx),
+    if !sessions_dir().join(name).exists() {
+        return Ok(Vec::new());
+    }
+    let rows = session_index::read_page(name, limit, offset, query)
+        .into_iter()
+        .map(|(line, role, content, created_at)| SessionMessagePayload {
+            id: format!("{}-{}", created_at, line),
             role,
             content,
             created_at,
-            line: idx,
-        });
-    }
-
-    let total = rows.len();
-    if offset >= total {
-        return Ok(Vec::new());
-    }
-    let end = total.saturating_sub(offset);
-    let start = end.saturating_sub(limit);
-    let slice = rows[start..end].to_vec();
-    Ok(slice)
+            line,
+        })
+        .collect();
+    Ok(rows)
 }
 
 #[tauri::command]
@@ -1090,11 +1492,13 @@ fn list_sessions() -> Result<Vec<SessionInfo>, String> {
             .unwrap_or_default()
             .to_string_lossy()
             .to_string();
+        let message_count = session_index::message_count(&name);
         items.push(SessionInfo {
             name: name.clone(),
             path: path.to_string_lossy().to_string(),
             size,
             modified,
+            message_count,
         });
     }
     items.sort_by(|a, b| b.modified.cmp(&a.modified));
@@ -1114,6 +1518,25 @@ fn read_session_messages(
     read_session_file(&name, limit.max(1), offset, query.as_deref())
 }
 
+#[tauri::command]
+fn search_all_sessions(
+    query: String,
+    limit: usize,
+) -> Result<Vec<SessionSearchResultPayload>, String> {
+    let rows = search_index::search(&query, limit.max(1))
+        .into_iter()
+        .map(|(session, line, role, content, created_at)| SessionSearchResultPayload {
+            id: format!("{session}-{created_at}-{line}"),
+            session,
+            role,
+            content,
+            created_at,
+            line,
+        })
+        .collect();
+    Ok(rows)
+}
+
 #[tauri::command]
 fn delete_session_line(name: String, line: usize) -> Result<(), String> {
     if name.contains(std::path::MAIN_SEPARATOR) {
@@ -1206,27 +1629,18 @@ fn start_process(
 fn stop_process(kind: String, state: State<Arc<Mutex<ProcState>>>) -> Result<(), String> {
     let mut guard = state.lock().map_err(|_| "state lock".to_string())?;
     match kind.as_str() {
-        "agent" => {
-            if let Some(mut child) = guard.agent.take() {
-                let pid = child.id();
-                let _ = child.kill();
-                kill_process_tree(pid);
-            }
-            kill_matching_processes("agent");
-        }
-        "gateway" => {
-            if let Some(mut child) = guard.gateway.take() {
-                let pid = child.id();
-                let _ = child.kill();
-                kill_process_tree(pid);
-            }
-            kill_matching_processes("gateway");
-        }
+        "agent" => stop_agent_locked(&mut guard),
+        "gateway" => stop_gateway_locked(&mut guard),
         _ => return Err("unknown process".to_string()),
     }
     Ok(())
 }
 
+/// Send a chat message by creating a persisted `jobs::JobRecord` for it and
+/// blocking (off the async executor) until that job reaches a terminal
+/// state, instead of running an ad-hoc subprocess that would vanish with no
+/// trace if the app closed mid-run. `jobs::execute_job` streams each output
+/// line to the chat UI as an `agent-chunk` event while this call waits.
 #[tauri::command]
 async fn send_agent_message(
     app: AppHandle,
@@ -1239,49 +1653,69 @@ async fn send_agent_message(
         format!("User: {}", truncate_line(&message, 200)),
         "stdout",
     );
-    let app_handle = app.clone();
-    let combined = tauri::async_runtime::spawn_blocking(move || -> Result<String, String> {
-        let mut cmd = base_command(&app_handle);
-        cmd.args([
-            "-m",
-            "nanobot",
-            "agent",
-            "--message",
-            &message,
-            "--session",
-            &session_id,
-        ])
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .stdin(Stdio::null());
+    let worker_id = format!("message:{session_id}");
+    workers::touch(&app, &worker_id, "message");
 
-        let output = cmd.output().map_err(|e| e.to_string())?;
-        let mut combined = String::new();
-        combined.push_str(&String::from_utf8_lossy(&output.stdout));
-        combined.push_str(&String::from_utf8_lossy(&output.stderr));
-
-        Ok(combined)
+    let app_handle = app.clone();
+    let worker_id_for_throttle = worker_id.clone();
+    let job = tauri::async_runtime::spawn_blocking(move || {
+        workers::throttle(&worker_id_for_throttle);
+        jobs::spawn(app_handle, message, session_id)
     })
     .await
-    .map_err(|e| e.to_string())??;
+    .map_err(|e| e.to_string())?;
 
-    let cleaned = strip_ansi(combined.as_str());
-    for line in cleaned.lines() {
-        if !line.trim().is_empty() {
-            emit_log(&app, "agent", line.to_string(), "stdout");
+    let job_id = job.id;
+    let final_record = tauri::async_runtime::spawn_blocking(move || loop {
+        if let Some(record) = jobs::get_job(&job_id) {
+            if !matches!(record.state, jobs::JobState::Running | jobs::JobState::Paused) {
+                return record;
+            }
         }
+        std::thread::sleep(Duration::from_millis(150));
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let cleaned = final_record.output.trim().to_string();
+    if !matches!(final_record.state, jobs::JobState::Completed) {
+        workers::mark_dead(&app, &worker_id, "message", cleaned.clone());
+        return Err(cleaned);
     }
-    Ok(cleaned.trim().to_string())
+
+    let _ = app.emit(
+        "agent-done",
+        AgentDonePayload {
+            session_id: final_record.session_id,
+            text: cleaned.clone(),
+        },
+    );
+    Ok(cleaned)
+}
+
+#[tauri::command]
+fn list_jobs() -> Vec<jobs::JobRecord> {
+    jobs::list_job_records()
+}
+
+#[tauri::command]
+fn resume_job(app: AppHandle, id: String) -> Result<jobs::JobRecord, String> {
+    jobs::resume(app, id)
 }
 
-fn truncate_line(s: &str, max_len: usize) -> String {
+#[tauri::command]
+fn cancel_job(app: AppHandle, id: String) -> Result<(), String> {
+    jobs::cancel(&app, id)
+}
+
+pub(crate) fn truncate_line(s: &str, max_len: usize) -> String {
     if s.chars().count() <= max_len {
         return s.to_string();
     }
     s.chars().take(max_len).collect::<String>() + "..."
 }
 
-fn strip_ansi(input: &str) -> String {
+pub(crate) fn strip_ansi(input: &str) -> String {
     let mut out = String::with_capacity(input.len());
     let mut chars = input.chars().peekable();
     while let Some(ch) = chars.next() {
@@ -1333,12 +1767,11 @@ fn main() {
                         }
                     }
                     "quit" => {
-                        // Ensure child processes exit with the desktop app.
+                        // Process-group/job ownership makes this deterministic:
+                        // no need to scan for stray processes afterwards.
                         let state = app.state::<Arc<Mutex<ProcState>>>().inner().clone();
                         stop_all_processes(&state);
-                        // Best-effort cleanup for any lingering nanobot processes.
-                        kill_matching_processes("agent");
-                        kill_matching_processes("gateway");
+                        jobs::pause_all_running();
                         app.exit(0);
                     }
                     _ => {}
@@ -1363,8 +1796,22 @@ fn main() {
             let state = app.state::<Arc<Mutex<ProcState>>>().inner().clone();
             let handle = app.handle().clone();
             if config_path().exists() {
-                let _ = start_process_inner("agent", &state, &handle);
-                let _ = start_process_inner("gateway", &state, &handle);
+                let startup_state = state.clone();
+                let startup_handle = handle.clone();
+                std::thread::spawn(move || {
+                    if start_with_backoff("gateway", &startup_state, &startup_handle).is_ok()
+                        && !wait_for_gateway_ready(&startup_state)
+                    {
+                        emit_log(
+                            &startup_handle,
+                            "agent",
+                            "Gateway did not become ready in time; starting agent anyway."
+                                .to_string(),
+                            "stderr",
+                        );
+                    }
+                    let _ = start_with_backoff("agent", &startup_state, &startup_handle);
+                });
             } else {
                 emit_log(
                     &handle,
@@ -1378,6 +1825,10 @@ fn main() {
                 emit_config_missing(&handle);
             }
 
+            jobs::resume_interrupted(&handle);
+            workers::spawn_idle_sweeper(handle.clone());
+            watcher::spawn(handle);
+
             Ok(())
         })
         .on_window_event(|window, event| {
@@ -1390,6 +1841,7 @@ fn main() {
             get_status,
             get_logs,
             set_log_streaming,
+            set_log_level,
             list_workspace_skills,
             read_skill_file,
             save_skill_file,
@@ -1404,13 +1856,18 @@ fn main() {
             read_session_history,
             list_sessions,
             read_session_messages,
+            search_all_sessions,
             delete_session_line,
             delete_session_lines,
             read_cron_jobs,
             delete_cron_job,
             start_process,
             stop_process,
-            send_agent_message
+            send_agent_message,
+            list_jobs,
+            resume_job,
+            cancel_job,
+            workers::list_workers
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
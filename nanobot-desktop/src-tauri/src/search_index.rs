@@ -0,0 +1,180 @@
+//! Cross-session full-text search. Builds an inverted index (lowercase
+//! token -> postings) over every session's messages, reusing
+//! `session_index`'s per-session scan rather than re-parsing files itself.
+//! The index is persisted under `nanobot_home()/search.idx` (MessagePack,
+//! matching `jobs.rs`/`session_index.rs`) and refreshed incrementally: only
+//! sessions whose mtime/size changed since the last search are re-tokenized.
+
+use crate::{nanobot_home, sessions_dir};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::UNIX_EPOCH;
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Posting {
+    session: String,
+    line: usize,
+    offset: u64,
+    len: u64,
+    role: String,
+    created_at: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+struct SearchIndex {
+    terms: HashMap<String, Vec<Posting>>,
+    /// Session name -> `(mtime, size)` it was last tokenized at.
+    stamps: HashMap<String, (u64, u64)>,
+}
+
+fn state() -> &'static Mutex<Option<SearchIndex>> {
+    static STATE: OnceLock<Mutex<Option<SearchIndex>>> = OnceLock::new();
+    STATE.get_or_init(|| Mutex::new(None))
+}
+
+fn index_path() -> PathBuf {
+    nanobot_home().join("search.idx")
+}
+
+fn load_from_disk() -> SearchIndex {
+    std::fs::read(index_path())
+        .ok()
+        .and_then(|bytes| rmp_serde::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+fn save_to_disk(index: &SearchIndex) {
+    if let Ok(bytes) = rmp_serde::to_vec(index) {
+        let _ = std::fs::write(index_path(), bytes);
+    }
+}
+
+fn tokenize(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(|token| token.to_lowercase())
+        .collect()
+}
+
+/// Re-tokenize any session whose mtime/size has changed since it was last
+/// indexed, and drop postings for sessions that no longer exist.
+fn refresh(index: &mut SearchIndex) {
+    let dir = sessions_dir();
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(e) => e,
+        Err(_) => return,
+    };
+
+    let mut seen = HashSet::new();
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("jsonl") {
+            continue;
+        }
+        let name = match path.file_name().and_then(|s| s.to_str()) {
+            Some(n) => n.to_string(),
+            None => continue,
+        };
+        if name == "cli_direct.jsonl" {
+            // Skip the live chat session history, same as list_sessions/
+            // read_session_history, to avoid leaking it into search results.
+            continue;
+        }
+        seen.insert(name.clone());
+
+        let metadata = match entry.metadata() {
+            Ok(m) => m,
+            Err(_) => continue,
+        };
+        let mtime = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let size = metadata.len();
+        if index.stamps.get(&name) == Some(&(mtime, size)) {
+            continue;
+        }
+
+        for postings in index.terms.values_mut() {
+            postings.retain(|p| p.session != name);
+        }
+        for (line, offset, len, role, created_at, fingerprint) in
+            crate::session_index::entries(&name)
+        {
+            for token in tokenize(&fingerprint) {
+                index.terms.entry(token).or_default().push(Posting {
+                    session: name.clone(),
+                    line,
+                    offset,
+                    len,
+                    role: role.clone(),
+                    created_at: created_at.clone(),
+                });
+            }
+        }
+        index.stamps.insert(name.clone(), (mtime, size));
+    }
+
+    index.stamps.retain(|name, _| seen.contains(name));
+    for postings in index.terms.values_mut() {
+        postings.retain(|p| seen.contains(&p.session));
+    }
+    index.terms.retain(|_, postings| !postings.is_empty());
+}
+
+/// Search every session except `cli_direct.jsonl` for messages whose content
+/// contains all of `query`'s terms (AND semantics), ranked by recency. Terms
+/// are split on any non-alphanumeric character and matched as whole
+/// lowercase tokens. Returns `(session, line, role, content, created_at)`
+/// tuples.
+pub(crate) fn search(query: &str, limit: usize) -> Vec<(String, usize, String, String, String)> {
+    let terms: Vec<String> = tokenize(query).into_iter().collect();
+    if terms.is_empty() {
+        return Vec::new();
+    }
+
+    let mut guard = match state().lock() {
+        Ok(g) => g,
+        Err(_) => return Vec::new(),
+    };
+    if guard.is_none() {
+        *guard = Some(load_from_disk());
+    }
+    let index = guard.as_mut().expect("just populated");
+    refresh(index);
+    save_to_disk(index);
+
+    let mut per_term: Vec<&Vec<Posting>> = Vec::with_capacity(terms.len());
+    for term in &terms {
+        match index.terms.get(term) {
+            Some(postings) => per_term.push(postings),
+            None => return Vec::new(),
+        }
+    }
+    per_term.sort_by_key(|postings| postings.len());
+
+    let mut candidates: HashMap<(String, usize), &Posting> = per_term[0]
+        .iter()
+        .map(|p| ((p.session.clone(), p.line), p))
+        .collect();
+    for postings in &per_term[1..] {
+        let keys: HashSet<(String, usize)> =
+            postings.iter().map(|p| (p.session.clone(), p.line)).collect();
+        candidates.retain(|key, _| keys.contains(key));
+    }
+
+    let mut hits: Vec<&Posting> = candidates.into_values().collect();
+    hits.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    hits.truncate(limit.max(1));
+
+    hits.into_iter()
+        .filter_map(|p| {
+            let content = crate::session_index::read_content_at(&p.session, p.offset, p.len)?;
+            Some((p.session.clone(), p.line, p.role.clone(), content, p.created_at.clone()))
+        })
+        .collect()
+}
@@ -0,0 +1,253 @@
+//! Offset-indexed session store. `read_session_file` used to re-read and
+//! re-parse every line of a session's `.jsonl` on every call, which gets
+//! expensive once a session grows into the thousands of lines. This module
+//! keeps a sidecar index (byte offset/length, role, timestamp and a
+//! lowercase content fingerprint per message) next to each session file,
+//! rebuilt only when the file's mtime/size changes, so pagination can seek
+//! straight to the rows it needs and `query` filtering can reject most
+//! lines from the fingerprint alone before touching the file again.
+
+use crate::sessions_dir;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::UNIX_EPOCH;
+
+#[derive(Serialize, Deserialize, Clone)]
+struct IndexEntry {
+    offset: u64,
+    len: u64,
+    line: usize,
+    role: String,
+    created_at: String,
+    /// Lowercased message content, used to test `query` without re-parsing
+    /// the line's JSON.
+    fingerprint: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct SessionIndex {
+    mtime: u64,
+    size: u64,
+    entries: Vec<IndexEntry>,
+}
+
+fn cache() -> &'static Mutex<HashMap<String, SessionIndex>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, SessionIndex>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn index_dir() -> PathBuf {
+    sessions_dir().join(".index")
+}
+
+fn index_path(name: &str) -> PathBuf {
+    index_dir().join(format!("{name}.idx"))
+}
+
+fn file_stamp(path: &std::path::Path) -> Option<(u64, u64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    let mtime = metadata
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_secs();
+    Some((mtime, metadata.len()))
+}
+
+/// Scan `name`'s session file line by line, recording each message's byte
+/// range and a lowercase fingerprint of its content.
+fn rebuild(name: &str, mtime: u64, size: u64) -> SessionIndex {
+    let path = sessions_dir().join(name);
+    let data = std::fs::read(&path).unwrap_or_default();
+    let mut entries = Vec::new();
+    let mut line_no = 0usize;
+    let mut offset = 0usize;
+
+    for raw_line in data.split(|b| *b == b'\n') {
+        let len = raw_line.len();
+        let start = offset;
+        offset += len + 1; // account for the stripped '\n'
+        let trimmed = std::str::from_utf8(raw_line).unwrap_or("").trim();
+        if trimmed.is_empty() {
+            line_no += 1;
+            continue;
+        }
+        let val: Value = match serde_json::from_str(trimmed) {
+            Ok(v) => v,
+            Err(_) => {
+                line_no += 1;
+                continue;
+            }
+        };
+        if val.get("_type").is_some() {
+            line_no += 1;
+            continue;
+        }
+        let content = val.get("content").and_then(Value::as_str).unwrap_or("");
+        if content.is_empty() {
+            line_no += 1;
+            continue;
+        }
+        let role = val
+            .get("role")
+            .and_then(Value::as_str)
+            .unwrap_or("system")
+            .to_string();
+        let created_at = val
+            .get("timestamp")
+            .or_else(|| val.get("created_at"))
+            .or_else(|| val.get("updated_at"))
+            .and_then(Value::as_str)
+            .unwrap_or("unknown")
+            .to_string();
+
+        entries.push(IndexEntry {
+            offset: start as u64,
+            len: len as u64,
+            line: line_no,
+            role,
+            created_at,
+            fingerprint: content.to_lowercase(),
+        });
+        line_no += 1;
+    }
+
+    let index = SessionIndex {
+        mtime,
+        size,
+        entries,
+    };
+    let _ = std::fs::create_dir_all(index_dir());
+    if let Ok(bytes) = rmp_serde::to_vec(&index) {
+        let _ = std::fs::write(index_path(name), bytes);
+    }
+    index
+}
+
+fn load_sidecar(name: &str) -> Option<SessionIndex> {
+    let bytes = std::fs::read(index_path(name)).ok()?;
+    rmp_serde::from_slice(&bytes).ok()
+}
+
+/// Return a freshly validated index for `name`, rebuilding it from the
+/// session file if the file was created, modified, or resized since the
+/// index was last built.
+fn ensure_fresh(name: &str) -> SessionIndex {
+    let path = sessions_dir().join(name);
+    let (mtime, size) = file_stamp(&path).unwrap_or((0, 0));
+
+    if let Ok(mut guard) = cache().lock() {
+        if let Some(existing) = guard.get(name) {
+            if existing.mtime == mtime && existing.size == size {
+                return existing.clone();
+            }
+        }
+    }
+
+    if let Some(existing) = load_sidecar(name) {
+        if existing.mtime == mtime && existing.size == size {
+            if let Ok(mut guard) = cache().lock() {
+                guard.insert(name.to_string(), existing.clone());
+            }
+            return existing;
+        }
+    }
+
+    let fresh = rebuild(name, mtime, size);
+    if let Ok(mut guard) = cache().lock() {
+        guard.insert(name.to_string(), fresh.clone());
+    }
+    fresh
+}
+
+/// Number of indexed messages in `name`'s session file, rebuilding the
+/// index first if it's stale. Used by `list_sessions` to report message
+/// counts without scanning every file on every poll.
+pub(crate) fn message_count(name: &str) -> usize {
+    ensure_fresh(name).entries.len()
+}
+
+/// Every indexed message in `name`, as `(line, offset, len, role, created_at,
+/// fingerprint)`. Used by `search_index` to build its inverted index without
+/// re-scanning session files itself.
+pub(crate) fn entries(name: &str) -> Vec<(usize, u64, u64, String, String, String)> {
+    ensure_fresh(name)
+        .entries
+        .into_iter()
+        .map(|e| (e.line, e.offset, e.len, e.role, e.created_at, e.fingerprint))
+        .collect()
+}
+
+/// Seek `name`'s session file to `offset`/`len` and return the parsed
+/// message content, without rebuilding or consulting the index.
+pub(crate) fn read_content_at(name: &str, offset: u64, len: u64) -> Option<String> {
+    let path = sessions_dir().join(name);
+    let entry = IndexEntry {
+        offset,
+        len,
+        line: 0,
+        role: String::new(),
+        created_at: String::new(),
+        fingerprint: String::new(),
+    };
+    read_entry_content(&path, &entry)
+}
+
+/// Read a seeked, JSON-parsed line from `path` at the given byte range.
+fn read_entry_content(path: &std::path::Path, entry: &IndexEntry) -> Option<String> {
+    let mut file = std::fs::File::open(path).ok()?;
+    file.seek(SeekFrom::Start(entry.offset)).ok()?;
+    let mut buf = vec![0u8; entry.len as usize];
+    file.read_exact(&mut buf).ok()?;
+    let trimmed = std::str::from_utf8(&buf).ok()?.trim();
+    let val: Value = serde_json::from_str(trimmed).ok()?;
+    val.get("content").and_then(Value::as_str).map(str::to_string)
+}
+
+/// Page over `name`'s messages counting back from the newest (`offset` 0 is
+/// the most recent `limit` rows), optionally restricted to rows whose
+/// fingerprint contains `query`. Each page is returned in file order, oldest
+/// row first. Only the rows that survive pagination
+/// are seeked and re-parsed for their content; every other row is resolved
+/// from the index alone.
+pub(crate) fn read_page(
+    name: &str,
+    limit: usize,
+    offset: usize,
+    query: Option<&str>,
+) -> Vec<(usize, String, String, String)> {
+    let index = ensure_fresh(name);
+    let lower_query = query.map(|q| q.to_lowercase());
+
+    let matching: Vec<&IndexEntry> = index
+        .entries
+        .iter()
+        .filter(|entry| {
+            lower_query
+                .as_ref()
+                .map(|q| entry.fingerprint.contains(q.as_str()))
+                .unwrap_or(true)
+        })
+        .collect();
+
+    let total = matching.len();
+    if offset >= total {
+        return Vec::new();
+    }
+    let end = total.saturating_sub(offset);
+    let start = end.saturating_sub(limit);
+
+    let path = sessions_dir().join(name);
+    matching[start..end]
+        .iter()
+        .filter_map(|entry| {
+            let content = read_entry_content(&path, entry)?;
+            Some((entry.line, entry.role.clone(), content, entry.created_at.clone()))
+        })
+        .collect()
+}
@@ -0,0 +1,182 @@
+//! Live file-watching for the skills/memory workspace, the sessions store,
+//! and `config.json`. Raw filesystem events are coalesced per-path so a
+//! burst of writes from an editor (or the agent itself) collapses into a
+//! single `*-changed` event for the frontend to react to.
+
+use crate::{
+    config_path, sessions_dir, validate_memory_name, validate_skill_name, workspace_memory_dir,
+    workspace_root, workspace_skills_dir,
+};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+/// How long a path must go quiet before its coalesced change is emitted.
+const COALESCE_WINDOW: Duration = Duration::from_millis(100);
+/// How often we re-check `config.json` for a workspace move. This periodic
+/// check is what detects the move; `config-changed` events do not trigger it.
+const ROOT_RECHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum WatchKind {
+    Skills,
+    Memory,
+    Sessions,
+    Config,
+}
+
+impl WatchKind {
+    fn event_name(self) -> &'static str {
+        match self {
+            WatchKind::Skills => "skills-changed",
+            WatchKind::Memory => "memory-changed",
+            WatchKind::Sessions => "sessions-changed",
+            WatchKind::Config => "config-changed",
+        }
+    }
+}
+
+struct WatchRoots {
+    skills: PathBuf,
+    memory: PathBuf,
+    sessions: PathBuf,
+    config: PathBuf,
+}
+
+impl WatchRoots {
+    fn current() -> Self {
+        Self {
+            skills: workspace_skills_dir(),
+            memory: workspace_memory_dir(),
+            sessions: sessions_dir(),
+            config: config_path(),
+        }
+    }
+
+    fn classify(&self, path: &Path) -> Option<WatchKind> {
+        if path == self.config {
+            return Some(WatchKind::Config);
+        }
+        if path.starts_with(&self.sessions) {
+            return Some(WatchKind::Sessions);
+        }
+        if path.starts_with(&self.skills) {
+            let name = path.strip_prefix(&self.skills).ok()?;
+            let top = name.components().next()?.as_os_str().to_str()?;
+            return validate_skill_name(top).ok().map(|_| WatchKind::Skills);
+        }
+        if path.starts_with(&self.memory) {
+            let name = path.file_name()?.to_str()?;
+            return validate_memory_name(name).ok().map(|_| WatchKind::Memory);
+        }
+        None
+    }
+
+    /// The set of directories that must exist for `notify` to watch them.
+    /// `config.json`'s parent is watched (not the file itself) so the
+    /// watch survives editors that replace the file via rename.
+    fn watch_targets(&self) -> Vec<PathBuf> {
+        let mut targets = vec![self.skills.clone(), self.memory.clone(), self.sessions.clone()];
+        if let Some(parent) = self.config.parent() {
+            targets.push(parent.to_path_buf());
+        }
+        targets
+    }
+}
+
+/// Spawn the watcher on a background thread for the lifetime of the app.
+/// Recreates the underlying `notify` watcher whenever `config.json`'s
+/// `agents.defaults.workspace` changes, since `workspace_root()` is
+/// config-derived and the skills/memory roots move with it.
+pub fn spawn(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        let roots = WatchRoots::current();
+        match run_session(&app, &roots) {
+            RestartReason::WorkspaceMoved => continue,
+            RestartReason::WatcherDied => {
+                std::thread::sleep(Duration::from_secs(1));
+                continue;
+            }
+        }
+    });
+}
+
+enum RestartReason {
+    WorkspaceMoved,
+    WatcherDied,
+}
+
+fn run_session(app: &AppHandle, roots: &WatchRoots) -> RestartReason {
+    let (tx, rx) = channel::<notify::Result<Event>>();
+    let mut watcher = match RecommendedWatcher::new(
+        move |res| {
+            let _ = tx.send(res);
+        },
+        notify::Config::default(),
+    ) {
+        Ok(w) => w,
+        Err(_) => return RestartReason::WatcherDied,
+    };
+
+    for target in roots.watch_targets() {
+        let _ = std::fs::create_dir_all(&target);
+        let _ = watcher.watch(&target, RecursiveMode::Recursive);
+    }
+
+    let initial_workspace = workspace_root();
+    let mut pending: HashMap<(WatchKind, PathBuf), Instant> = HashMap::new();
+    let mut last_root_check = Instant::now();
+
+    loop {
+        match rx.recv_timeout(COALESCE_WINDOW) {
+            Ok(Ok(event)) => {
+                if !matches!(
+                    event.kind,
+                    EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+                ) {
+                    continue;
+                }
+                for path in &event.paths {
+                    if let Some(kind) = roots.classify(path) {
+                        pending.insert((kind, path.clone()), Instant::now());
+                    }
+                }
+            }
+            Ok(Err(_)) => return RestartReason::WatcherDied,
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return RestartReason::WatcherDied,
+        }
+
+        flush_ready(app, &mut pending);
+
+        if last_root_check.elapsed() >= ROOT_RECHECK_INTERVAL {
+            last_root_check = Instant::now();
+            if workspace_root() != initial_workspace {
+                flush_ready(app, &mut pending);
+                return RestartReason::WorkspaceMoved;
+            }
+        }
+    }
+}
+
+fn flush_ready(app: &AppHandle, pending: &mut HashMap<(WatchKind, PathBuf), Instant>) {
+    let now = Instant::now();
+    let ready: Vec<(WatchKind, PathBuf)> = pending
+        .iter()
+        .filter(|(_, last_seen)| now.duration_since(**last_seen) >= COALESCE_WINDOW)
+        .map(|(key, _)| key.clone())
+        .collect();
+    if ready.is_empty() {
+        return;
+    }
+    let mut emitted: HashMap<WatchKind, ()> = HashMap::new();
+    for key @ (kind, _) in &ready {
+        pending.remove(key);
+        if emitted.insert(*kind, ()).is_none() {
+            let _ = app.emit(kind.event_name(), ());
+        }
+    }
+}
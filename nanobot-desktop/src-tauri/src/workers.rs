@@ -0,0 +1,182 @@
+//! Background worker registry. `ProcState` only tracks raw `Child` handles,
+//! which tells the UI whether the agent/gateway processes are *managed* but
+//! not whether they're actually doing anything. This mirrors that with a
+//! lifecycle (`Active`/`Idle`/`Dead`) per worker, a last-activity timestamp
+//! driven by `emit_log`, and a last-error field for failed spawns/exits.
+
+use crate::config_path;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+
+/// How long a worker can go quiet before the sweeper downgrades it from
+/// `Active` to `Idle`.
+const IDLE_AFTER_SECS: u64 = 15;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum WorkerLifecycle {
+    Active,
+    Idle,
+    Dead,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WorkerRecord {
+    id: String,
+    kind: String,
+    state: WorkerLifecycle,
+    last_activity: u64,
+    last_error: Option<String>,
+}
+
+#[derive(Default)]
+struct Registry {
+    workers: HashMap<String, WorkerRecord>,
+    last_invocation: HashMap<String, Instant>,
+}
+
+fn registry() -> &'static Mutex<Registry> {
+    static REGISTRY: OnceLock<Mutex<Registry>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(Registry::default()))
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Mark a worker as active and bump its last-activity timestamp. Called
+/// from `emit_log` for every log line attributed to a worker, and from
+/// `send_agent_message` at the start of each in-flight invocation.
+pub(crate) fn touch(app: &AppHandle, id: &str, kind: &str) {
+    let mut emit_record = None;
+    if let Ok(mut reg) = registry().lock() {
+        let existing_state = reg.workers.get(id).map(|w| w.state);
+        let entry = reg.workers.entry(id.to_string()).or_insert(WorkerRecord {
+            id: id.to_string(),
+            kind: kind.to_string(),
+            state: WorkerLifecycle::Active,
+            last_activity: 0,
+            last_error: None,
+        });
+        entry.last_activity = now_secs();
+        entry.last_error = None;
+        entry.state = WorkerLifecycle::Active;
+        if existing_state != Some(WorkerLifecycle::Active) {
+            emit_record = Some(entry.clone());
+        }
+    }
+    if let Some(record) = emit_record {
+        let _ = app.emit("worker-state-changed", record);
+    }
+}
+
+/// Mark a worker `Dead` with the given error, e.g. a failed spawn or a
+/// non-zero exit. Always emits, since a new error is always worth surfacing.
+pub(crate) fn mark_dead(app: &AppHandle, id: &str, kind: &str, error: String) {
+    let record = if let Ok(mut reg) = registry().lock() {
+        let entry = reg.workers.entry(id.to_string()).or_insert(WorkerRecord {
+            id: id.to_string(),
+            kind: kind.to_string(),
+            state: WorkerLifecycle::Dead,
+            last_activity: now_secs(),
+            last_error: None,
+        });
+        entry.state = WorkerLifecycle::Dead;
+        entry.last_activity = now_secs();
+        entry.last_error = Some(error);
+        Some(entry.clone())
+    } else {
+        None
+    };
+    if let Some(record) = record {
+        let _ = app.emit("worker-state-changed", record);
+    }
+}
+
+#[tauri::command]
+pub(crate) fn list_workers() -> Vec<WorkerRecord> {
+    registry()
+        .lock()
+        .map(|reg| {
+            let mut workers: Vec<WorkerRecord> = reg.workers.values().cloned().collect();
+            workers.sort_by(|a, b| a.id.cmp(&b.id));
+            workers
+        })
+        .unwrap_or_default()
+}
+
+/// Periodically downgrade workers that have gone quiet for `IDLE_AFTER_SECS`
+/// from `Active` to `Idle`, so the registry reflects reality even between
+/// `touch` calls.
+pub(crate) fn spawn_idle_sweeper(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(Duration::from_secs(5));
+        let mut newly_idle = Vec::new();
+        if let Ok(mut reg) = registry().lock() {
+            let now = now_secs();
+            for worker in reg.workers.values_mut() {
+                if worker.state == WorkerLifecycle::Active
+                    && now.saturating_sub(worker.last_activity) >= IDLE_AFTER_SECS
+                {
+                    worker.state = WorkerLifecycle::Idle;
+                    newly_idle.push(worker.clone());
+                }
+            }
+        }
+        for record in newly_idle {
+            let _ = app.emit("worker-state-changed", record);
+        }
+    });
+}
+
+fn read_tranquility_ms() -> u64 {
+    let contents = match std::fs::read_to_string(config_path()) {
+        Ok(c) => c,
+        Err(_) => return 0,
+    };
+    let parsed: Value = match serde_json::from_str(&contents) {
+        Ok(v) => v,
+        Err(_) => return 0,
+    };
+    parsed
+        .get("workers")
+        .and_then(|w| w.get("tranquility_ms"))
+        .and_then(Value::as_u64)
+        .unwrap_or(0)
+}
+
+/// Block the caller until at least the configured tranquility window has
+/// elapsed since this worker's previous invocation, to avoid hammering the
+/// backend with rapid repeated agent calls.
+pub(crate) fn throttle(id: &str) {
+    let min_gap = read_tranquility_ms();
+    if min_gap == 0 {
+        return;
+    }
+    let min_gap = Duration::from_millis(min_gap);
+    let mut sleep_for = None;
+    if let Ok(mut reg) = registry().lock() {
+        let now = Instant::now();
+        if let Some(last) = reg.last_invocation.get(id) {
+            let next_allowed = *last + min_gap;
+            if next_allowed > now {
+                sleep_for = Some(next_allowed - now);
+            }
+        }
+        // Record when this invocation will actually proceed, so a caller
+        // arriving while we sleep is spaced from that, not from our arrival.
+        reg.last_invocation
+            .insert(id.to_string(), now + sleep_for.unwrap_or_default());
+    }
+    if let Some(duration) = sleep_for {
+        std::thread::sleep(duration);
+    }
+}